@@ -1,15 +1,14 @@
 use std::fs;
 use std::path::Path;
-use std::process::Command;
+use std::process::{Command, Stdio};
 use colored::*;
 use std::path::PathBuf;
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use thiserror::Error;
+use std::io::{self, BufRead, BufReader, Read as _, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
-use std::io::{self, Write};
 
 #[derive(Error, Debug)]
 pub enum AppError {
@@ -21,80 +20,262 @@ pub enum AppError {
     PathError(String),
 }
 
+/// Controls how the output CRF for a conversion is chosen.
+enum QualityMode {
+    /// Always use the given CRF value.
+    FixedCrf(u32),
+    /// Binary-search for the CRF that achieves the given VMAF score.
+    TargetVmaf(f64),
+}
+
+/// Lowest (best quality) CRF considered when searching for a target VMAF.
+const MIN_SEARCH_CRF: u32 = 18;
+/// Highest (most compressed) CRF considered when searching for a target VMAF.
+const MAX_SEARCH_CRF: u32 = 34;
+/// Acceptable gap between the measured VMAF score and the target before the search stops.
+const VMAF_TOLERANCE: f64 = 1.0;
+/// Number of short clips sampled across the timeline to estimate VMAF at a given CRF.
+const PROBE_SEGMENT_COUNT: usize = 3;
+/// Length in seconds of each sampled probe segment.
+const PROBE_SEGMENT_SECS: f64 = 2.0;
+
+/// Video/audio codec settings applied to sources whose resolution falls at
+/// or above a profile's `min_height`.
+#[derive(Clone)]
+struct CodecProfile {
+    /// Minimum source height (in pixels) this profile applies to.
+    min_height: u32,
+    video_codec: &'static str,
+    video_preset: &'static str,
+    /// Fixed video bitrate for this profile, e.g. "6M" for AV1. When `None`,
+    /// the caller's CRF (fixed or VMAF-derived) is used instead, or
+    /// `HW_ENCODER_FALLBACK_BITRATE` when a hardware encoder is selected.
+    video_bitrate: Option<&'static str>,
+    audio_codec: &'static str,
+    audio_bitrate: &'static str,
+}
+
+/// Maps source resolution to codec profile, highest `min_height` first so
+/// the first match wins. Exposed as a config struct so the table can be
+/// overridden rather than hardcoded into `convert_mov_to_mp4`.
+struct ResolutionProfiles {
+    profiles: Vec<CodecProfile>,
+}
+
+impl Default for ResolutionProfiles {
+    fn default() -> Self {
+        Self {
+            profiles: vec![
+                // WQHD/UHD: AV1 + Opus for far better compression at high resolutions.
+                CodecProfile {
+                    min_height: 1440,
+                    video_codec: "libsvtav1",
+                    video_preset: "8",
+                    video_bitrate: Some("6M"),
+                    audio_codec: "libopus",
+                    audio_bitrate: "128k",
+                },
+                // nHD/HD/FullHD: H.264 + AAC for maximum compatibility.
+                CodecProfile {
+                    min_height: 0,
+                    video_codec: "libx264",
+                    video_preset: "faster",
+                    video_bitrate: None,
+                    audio_codec: "aac",
+                    audio_bitrate: "128k",
+                },
+            ],
+        }
+    }
+}
+
+impl ResolutionProfiles {
+    /// Returns the first profile whose `min_height` the source satisfies.
+    /// `profiles` must be sorted by descending `min_height` with a `0` fallback.
+    fn profile_for(&self, height: u32) -> &CodecProfile {
+        self.profiles.iter()
+            .find(|profile| height >= profile.min_height)
+            .unwrap_or_else(|| self.profiles.last().expect("profile table must not be empty"))
+    }
+}
+
 fn main() -> Result<(), AppError> {
-    print!("Do you want to delete MOV files after conversion? (yes/no): ");
-    io::stdout().flush()?;
-    
-    let mut input = String::new();
-    io::stdin().read_line(&mut input)?;
-    let delete_after = input.trim().to_lowercase() == "yes" || input.trim().to_lowercase() == "y";
-    
+    let delete_after = prompt_yes_no("Do you want to delete MOV files after conversion? (yes/no): ")?;
+
+    let quality_mode = prompt_quality_mode()?;
+    let resolution_profiles = ResolutionProfiles::default();
+    let worker_count = prompt_worker_count(default_worker_count())?;
+    let use_hw_encoder = prompt_yes_no("Use a hardware video encoder if available? (yes/no): ")?;
+
     let ffmpeg_path = get_ffmpeg_path()?;
+    let ffprobe_path = get_ffprobe_path()?;
     let mov_filenames = get_all_mov()?;
     let total = mov_filenames.len();
-    println!("Found {} MOV files to process", total);
-    
-    let mut success = 0;
-    let mut failed = 0;
-    
-    let pb = ProgressBar::new(total as u64);
-    pb.set_style(ProgressStyle::default_bar()
-        .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len}\n\
-                  Current: {msg}")
+    println!("Found {} MOV files to process with {} worker(s)", total, worker_count);
+
+    // Cap each FFmpeg invocation's own threading so `worker_count` concurrent
+    // jobs don't oversubscribe the available CPU cores.
+    let total_cpus = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let ffmpeg_threads = (total_cpus / worker_count).max(1) as u32;
+
+    let jobs: Mutex<Vec<(usize, String)>> = Mutex::new(mov_filenames.into_iter().enumerate().rev().collect());
+    let jobs = Arc::new(jobs);
+    let ffmpeg_path = Arc::new(ffmpeg_path);
+    let ffprobe_path = Arc::new(ffprobe_path);
+    let quality_mode = Arc::new(quality_mode);
+    let resolution_profiles = Arc::new(resolution_profiles);
+    let success = Arc::new(AtomicUsize::new(0));
+    let failed = Arc::new(AtomicUsize::new(0));
+
+    let multi_progress = MultiProgress::new();
+    let overall_pb = multi_progress.add(ProgressBar::new(total as u64));
+    overall_pb.set_style(ProgressStyle::default_bar()
+        .template("{spinner:.green} [{elapsed_precise}] [{bar:40.green/blue}] {pos}/{len} files completed {msg}")
         .unwrap()
         .progress_chars("█▓▒░"));
-    
-    for mov_filename in mov_filenames {
-        let display_name = Path::new(&mov_filename)
-            .file_name()
-            .and_then(|f| f.to_str())
-            .unwrap_or(&mov_filename);
-        
-        pb.set_message(format!("Converting: {}", display_name));
-        
-        // Create a flag to control the progress update thread
-        let should_continue = Arc::new(AtomicBool::new(true));
-        let should_continue_clone = Arc::clone(&should_continue);
-        
-        // Start a thread to update the progress bar
-        let pb_clone = pb.clone();
-        let handle = thread::spawn(move || {
-            while should_continue_clone.load(Ordering::Relaxed) {
-                thread::sleep(Duration::from_millis(100));
-                pb_clone.tick();
-            }
-        });
 
-        match convert_mov_to_mp4(&mov_filename, &ffmpeg_path) {
-            Ok(_) => {
-                if delete_after {
-                    let _ = remove_mov(&mov_filename);
+    let handles: Vec<_> = (0..worker_count).map(|_| {
+        let jobs = Arc::clone(&jobs);
+        let ffmpeg_path = Arc::clone(&ffmpeg_path);
+        let ffprobe_path = Arc::clone(&ffprobe_path);
+        let quality_mode = Arc::clone(&quality_mode);
+        let resolution_profiles = Arc::clone(&resolution_profiles);
+        let success = Arc::clone(&success);
+        let failed = Arc::clone(&failed);
+        let multi_progress = multi_progress.clone();
+        let overall_pb = overall_pb.clone();
+
+        thread::spawn(move || {
+            loop {
+                let job = jobs.lock().unwrap().pop();
+                let (index, mov_filename) = match job {
+                    Some(job) => job,
+                    None => break,
+                };
+
+                let display_name = Path::new(&mov_filename)
+                    .file_name()
+                    .and_then(|f| f.to_str())
+                    .unwrap_or(&mov_filename)
+                    .to_string();
+
+                let pb = multi_progress.add(ProgressBar::new(1000));
+                pb.set_style(ProgressStyle::default_bar()
+                    .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {percent}% (eta {eta}) [{pos}/{len}] {msg}")
+                    .unwrap()
+                    .progress_chars("█▓▒░"));
+                pb.set_message(format!("({}/{}) Converting: {}", index + 1, total, display_name));
+
+                let config = ConversionConfig {
+                    ffmpeg_path: &ffmpeg_path,
+                    ffprobe_path: &ffprobe_path,
+                    pb: &pb,
+                    quality_mode: &quality_mode,
+                    resolution_profiles: &resolution_profiles,
+                    ffmpeg_threads,
+                    use_hw_encoder,
+                };
+                let result = convert_mov_to_mp4(&mov_filename, &config);
+
+                match result {
+                    Ok(_) => {
+                        if delete_after {
+                            let _ = remove_mov(&mov_filename);
+                        }
+                        success.fetch_add(1, Ordering::Relaxed);
+                        pb.finish_and_clear();
+                        multi_progress.println(format!("{} Successfully converted: {}", "✓".green(), display_name)).ok();
+                    }
+                    Err(e) => {
+                        failed.fetch_add(1, Ordering::Relaxed);
+                        pb.finish_and_clear();
+                        multi_progress.println(format!("{} Failed to convert {}: {}", "✗".red(), display_name, e)).ok();
+                    }
                 }
-                success += 1;
-                pb.println(format!("{} Successfully converted: {}", "✓".green(), display_name));
-            }
-            Err(e) => {
-                failed += 1;
-                pb.println(format!("{} Failed to convert {}: {}", "✗".red(), display_name, e));
+
+                overall_pb.inc(1);
             }
-        }
-        
-        // Signal the thread to stop and wait for it
-        should_continue.store(false, Ordering::Relaxed);
-        handle.join().unwrap();
-        pb.inc(1);
-    }
-    
-    pb.finish_with_message("Conversion complete");
-    
+        })
+    }).collect();
+
+    for handle in handles {
+        handle.join().expect("conversion worker thread panicked");
+    }
+
+    overall_pb.finish_with_message("Conversion complete");
+
     println!("\nSummary:");
     println!("{} Total files processed", total);
-    println!("{} Successfully converted", success);
-    println!("{} Failed conversions", failed);
-    
+    println!("{} Successfully converted", success.load(Ordering::Relaxed));
+    println!("{} Failed conversions", failed.load(Ordering::Relaxed));
+
     Ok(())
 }
 
+/// Defaults to half the available CPU cores (minimum 1) so FFmpeg's own
+/// per-job threading still has room to use the rest.
+fn default_worker_count() -> usize {
+    let cpus = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    (cpus / 2).max(1)
+}
+
+/// Prompts for how many files to convert concurrently, falling back to
+/// `default_workers` when left blank or given an invalid value.
+fn prompt_worker_count(default_workers: usize) -> Result<usize, AppError> {
+    print!("How many files to convert in parallel? (blank for default {}): ", default_workers);
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    let trimmed = input.trim();
+
+    if trimmed.is_empty() {
+        return Ok(default_workers);
+    }
+
+    match trimmed.parse::<usize>() {
+        Ok(count) if count > 0 => Ok(count),
+        _ => {
+            println!("Invalid worker count, falling back to default {}", default_workers);
+            Ok(default_workers)
+        }
+    }
+}
+
+/// Prompts with a yes/no question and returns whether the user confirmed.
+fn prompt_yes_no(question: &str) -> Result<bool, AppError> {
+    print!("{}", question);
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    let trimmed = input.trim().to_lowercase();
+    Ok(trimmed == "yes" || trimmed == "y")
+}
+
+/// Prompts the user for a target VMAF score, falling back to the default
+/// fixed CRF when the input is left blank.
+fn prompt_quality_mode() -> Result<QualityMode, AppError> {
+    print!("Enter target VMAF score for quality-based CRF selection (blank for default CRF 23): ");
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    let trimmed = input.trim();
+
+    if trimmed.is_empty() {
+        return Ok(QualityMode::FixedCrf(23));
+    }
+
+    match trimmed.parse::<f64>() {
+        Ok(target_vmaf) => Ok(QualityMode::TargetVmaf(target_vmaf)),
+        Err(_) => {
+            println!("Invalid VMAF score, falling back to default CRF 23");
+            Ok(QualityMode::FixedCrf(23))
+        }
+    }
+}
+
 fn get_all_mov() -> Result<Vec<String>, AppError> {
     let mov_dir = Path::new("mov");
     let mut mov_filenames = Vec::new();
@@ -122,12 +303,34 @@ fn get_all_mov() -> Result<Vec<String>, AppError> {
     Ok(mov_filenames)
 }
 
+/// Per-job configuration for `convert_mov_to_mp4`, bundled into a struct so
+/// the worker threads in `main` can share it without the function itself
+/// tripping clippy's `too_many_arguments`.
+struct ConversionConfig<'a> {
+    ffmpeg_path: &'a str,
+    ffprobe_path: &'a str,
+    pb: &'a ProgressBar,
+    quality_mode: &'a QualityMode,
+    resolution_profiles: &'a ResolutionProfiles,
+    ffmpeg_threads: u32,
+    use_hw_encoder: bool,
+}
+
 /// Converts a MOV file to MP4 format using FFmpeg
-/// 
-/// This function handles the conversion process with optimized settings for 
+///
+/// This function handles the conversion process with optimized settings for
 /// fast conversion while maintaining good quality. It uses hardware acceleration
-/// when available on the platform.
-fn convert_mov_to_mp4(mov_filename: &str, ffmpeg_path: &str) -> Result<(), AppError> {    
+/// when available on the platform. Progress is reported on `config.pb` as a true
+/// percentage of the source duration, driven by FFmpeg's own `-progress` output.
+fn convert_mov_to_mp4(mov_filename: &str, config: &ConversionConfig) -> Result<(), AppError> {
+    let ffmpeg_path = config.ffmpeg_path;
+    let ffprobe_path = config.ffprobe_path;
+    let pb = config.pb;
+    let quality_mode = config.quality_mode;
+    let resolution_profiles = config.resolution_profiles;
+    let ffmpeg_threads = config.ffmpeg_threads;
+    let use_hw_encoder = config.use_hw_encoder;
+
     // Create mp4 directory if it doesn't exist
     let mp4_dir = Path::new("mp4");
     if !mp4_dir.exists() {
@@ -139,13 +342,48 @@ fn convert_mov_to_mp4(mov_filename: &str, ffmpeg_path: &str) -> Result<(), AppEr
         .file_name()
         .ok_or_else(|| AppError::PathError("Invalid filename".to_string()))?;
     let mp4_file = mp4_dir.join(file_name).with_extension("mp4");
-    
+
+    // Probe the source duration up front so progress can be reported as a
+    // percentage instead of an indeterminate spinner.
+    let duration_secs = get_duration_secs(ffmpeg_path, mov_filename)?;
+
+    // Probe the source resolution and frame rate in one pass: resolution
+    // picks a codec profile (H.264/AAC for nHD/HD/FullHD, AV1/Opus for
+    // WQHD/UHD), frame rate tells us whether the source is variable-frame-rate.
+    let video_info = probe_video_stream(ffprobe_path, mov_filename)?;
+    let profile = resolution_profiles.profile_for(video_info.height);
+
+    // Swap in the platform's real hardware encoder for the profile's software
+    // codec when the user opted in and the encoder actually works, otherwise
+    // stay on the software codec.
+    let video_codec = select_video_encoder(ffmpeg_path, profile, use_hw_encoder);
+    let is_hw_encoder = is_hardware_encoder(&video_codec);
+
+    // Resolve the CRF to encode with, binary-searching for one that hits the
+    // target VMAF score when the user opted into quality-based selection.
+    // Skip the search entirely when CRF won't even be used: profiles with a
+    // fixed `video_bitrate` (e.g. AV1) and hardware encoders both ignore it
+    // in favor of a bitrate, and the bisection is an expensive multi-encode
+    // probe loop that shouldn't run just to discard its result.
+    let crf_str = if profile.video_bitrate.is_none() && !is_hw_encoder {
+        let crf = match quality_mode {
+            QualityMode::FixedCrf(crf) => *crf,
+            QualityMode::TargetVmaf(target_vmaf) => {
+                find_crf_for_target_vmaf(ffmpeg_path, mov_filename, duration_secs, *target_vmaf)?
+            }
+        };
+        crf.to_string()
+    } else {
+        String::new()
+    };
+
     // ==========================================
     // FFmpeg conversion argument configuration
     // ==========================================
-    
-    // 1. Configure hardware acceleration based on OS
-    let hw_accel_args: Vec<&str> = if cfg!(target_os = "macos") {
+
+    // 1. Configure hardware-accelerated decode based on OS, and keep frames
+    //    on the GPU end-to-end when also encoding with VAAPI.
+    let mut hw_accel_args: Vec<&str> = if cfg!(target_os = "macos") {
         vec!["-hwaccel", "videotoolbox"]
     } else if cfg!(target_os = "windows") {
         vec!["-hwaccel", "dxva2"]
@@ -154,58 +392,338 @@ fn convert_mov_to_mp4(mov_filename: &str, ffmpeg_path: &str) -> Result<(), AppEr
     } else {
         vec![]
     };
+    if video_codec == "h264_vaapi" {
+        hw_accel_args.extend_from_slice(&["-hwaccel_output_format", "vaapi"]);
+    }
 
     // 2. Initialize the arguments list
     let mut args = Vec::new();
-    
+
     // 3. Add hardware acceleration (if available for platform)
     args.extend_from_slice(&hw_accel_args);
-    
+
     // 4. Specify input file
     args.extend_from_slice(&["-i", mov_filename]);
-    
-    // 5. Configure video codec settings
-    //    - libx264: High quality H.264 encoder
-    //    - faster preset: Good balance between speed and quality
-    //    - CRF 23: Default quality setting (lower = better quality)
-    args.extend_from_slice(&[
-        "-c:v", "libx264", 
-        "-preset", "faster",
-        "-crf", "23",
-    ]);
-    
-    // 6. Configure audio codec settings
-    //    - AAC: Industry standard audio codec
-    //    - 128k bitrate: Good quality for most audio sources
-    args.extend_from_slice(&[
-        "-c:a", "aac", 
-        "-b:a", "128k",
-    ]);
-    
-    // 7. Optimize for performance with multithreading
-    //    - 0 threads means auto-detect available CPU cores
-    args.extend_from_slice(&["-threads", "0"]);
-    
-    // 8. Specify output file
-    args.push(mp4_file.to_str()
-        .ok_or_else(|| AppError::PathError("Invalid MP4 path".to_string()))?);
-    
+
+    // 5. Configure video codec settings from the resolution-based profile
+    //    - profile.video_bitrate: fixed target bitrate (e.g. AV1 at high res)
+    //    - otherwise: CRF, fixed by default or auto-selected for a target VMAF
+    //    - hardware encoders don't take libx264's `-preset`/`-crf`, so they
+    //      fall back to a fixed bitrate instead
+    args.extend_from_slice(&["-c:v", &video_codec]);
+    if !is_hw_encoder {
+        args.extend_from_slice(&["-preset", profile.video_preset]);
+    }
+    if let Some(video_bitrate) = profile.video_bitrate {
+        args.extend_from_slice(&["-b:v", video_bitrate]);
+    } else if is_hw_encoder {
+        args.extend_from_slice(&["-b:v", HW_ENCODER_FALLBACK_BITRATE]);
+    } else {
+        args.extend_from_slice(&["-crf", &crf_str]);
+    }
+
+    // 6. Configure audio codec settings from the same profile
+    args.extend_from_slice(&["-c:a", profile.audio_codec, "-b:a", profile.audio_bitrate]);
+
+    // 7. Cap this job's own threading so concurrent worker-pool jobs don't
+    //    oversubscribe the available CPU cores.
+    let ffmpeg_threads_str = ffmpeg_threads.to_string();
+    args.extend_from_slice(&["-threads", &ffmpeg_threads_str]);
+
+    // 8. Preserve variable frame rate timing instead of forcing the source
+    //    onto a constant frame rate.
+    if video_info.is_vfr {
+        args.extend_from_slice(&["-fps_mode", "vfr"]);
+    }
+
+    // 9. Move the moov atom to the front so the output is playable while
+    //    still downloading/streaming.
+    args.extend_from_slice(&["-movflags", "+faststart"]);
+
+    // 10. Emit machine-readable progress on stdout instead of the human-readable stats
+    args.extend_from_slice(&["-progress", "pipe:1", "-nostats"]);
+
+    // 11. Specify output file
+    let mp4_file_str = mp4_file.to_str()
+        .ok_or_else(|| AppError::PathError("Invalid MP4 path".to_string()))?;
+    args.push(mp4_file_str);
+
     // ==========================================
     // Execute FFmpeg conversion command
     // ==========================================
-    
-    let output = Command::new(ffmpeg_path)
+
+    let mut child = Command::new(ffmpeg_path)
         .args(&args)
-        .output()?;
-    
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let stdout = child.stdout.take()
+        .ok_or_else(|| AppError::FFmpegError("Failed to capture FFmpeg stdout".to_string()))?;
+    let stderr = child.stderr.take()
+        .ok_or_else(|| AppError::FFmpegError("Failed to capture FFmpeg stderr".to_string()))?;
+
+    // Drain stderr on its own thread so a full OS pipe buffer (long or
+    // warning-heavy encodes) can't deadlock against the stdout progress loop
+    // below, which blocks waiting on ffmpeg the same way ffmpeg would block
+    // waiting on us to read stderr.
+    let stderr_handle = thread::spawn(move || {
+        let mut captured = String::new();
+        let _ = BufReader::new(stderr).read_to_string(&mut captured);
+        captured
+    });
+
+    for line in BufReader::new(stdout).lines() {
+        let line = line?;
+        if let Some(out_time_us) = line.strip_prefix("out_time_us=") {
+            if let Ok(out_time_us) = out_time_us.parse::<i64>() {
+                let elapsed_secs = (out_time_us.max(0) as f64) / 1_000_000.0;
+                let ratio = if duration_secs > 0.0 {
+                    (elapsed_secs / duration_secs).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                pb.set_position((ratio * pb.length().unwrap_or(1000) as f64) as u64);
+            }
+        } else if line == "progress=end" {
+            pb.set_position(pb.length().unwrap_or(1000));
+        }
+    }
+
+    let status = child.wait()?;
+    let stderr_output = stderr_handle.join()
+        .map_err(|_| AppError::FFmpegError("FFmpeg stderr reader thread panicked".to_string()))?;
+
     // Check if conversion was successful
-    if output.status.success() {
+    if status.success() {
         Ok(())
     } else {
-        Err(AppError::FFmpegError(String::from_utf8_lossy(&output.stderr).to_string()))
+        Err(AppError::FFmpegError(stderr_output))
     }
 }
 
+/// Binary-searches the CRF range for the highest value (smallest file) whose
+/// measured VMAF score is still within `VMAF_TOLERANCE` of `target_vmaf`, probing a handful of short
+/// segments sampled across the timeline instead of encoding the whole file.
+fn find_crf_for_target_vmaf(
+    ffmpeg_path: &str,
+    mov_filename: &str,
+    duration_secs: f64,
+    target_vmaf: f64,
+) -> Result<u32, AppError> {
+    let segment_starts = probe_segment_starts(duration_secs);
+
+    let job_token = probe_temp_token(mov_filename);
+
+    bisect_crf_for_target_vmaf(target_vmaf, |crf| {
+        measure_vmaf_at_crf(ffmpeg_path, mov_filename, &segment_starts, crf, &job_token)
+    })
+}
+
+/// Unique-enough token for this job's probe/VMAF-log temp files, so two
+/// conversions running concurrently in the worker pool and bisecting the
+/// same mid-CRF don't collide on the same `/tmp` path: pid + thread id
+/// rule out cross-job collisions, the source stem makes collisions easy to
+/// trace back to a file if one ever does slip through.
+fn probe_temp_token(mov_filename: &str) -> String {
+    let stem = Path::new(mov_filename)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("job");
+    let thread_id: String = format!("{:?}", thread::current().id())
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect();
+    format!("{}_{}_{}", std::process::id(), stem, thread_id)
+}
+
+/// Binary-searches `MIN_SEARCH_CRF..=MAX_SEARCH_CRF` for the highest CRF
+/// (smallest file) whose VMAF score, per `measure`, is still within
+/// `VMAF_TOLERANCE` of `target_vmaf`. Split out from `find_crf_for_target_vmaf`
+/// so the search itself can be unit-tested without shelling out to FFmpeg.
+fn bisect_crf_for_target_vmaf(
+    target_vmaf: f64,
+    mut measure: impl FnMut(u32) -> Result<f64, AppError>,
+) -> Result<u32, AppError> {
+    let mut low = MIN_SEARCH_CRF;
+    let mut high = MAX_SEARCH_CRF;
+    let mut best_crf = MIN_SEARCH_CRF;
+
+    while low <= high {
+        let mid = low + (high - low) / 2;
+        let score = measure(mid)?;
+
+        if score >= target_vmaf - VMAF_TOLERANCE {
+            // Quality target met (or close enough): this CRF is usable, try
+            // a higher CRF for a smaller file.
+            best_crf = mid;
+            if mid == MAX_SEARCH_CRF {
+                break;
+            }
+            low = mid + 1;
+        } else {
+            // Below target: need a lower CRF (higher quality).
+            if mid == MIN_SEARCH_CRF {
+                break;
+            }
+            high = mid - 1;
+        }
+    }
+
+    Ok(best_crf)
+}
+
+/// Picks up to `PROBE_SEGMENT_COUNT` start times spread evenly across the
+/// timeline, each long enough for a `PROBE_SEGMENT_SECS` sample.
+fn probe_segment_starts(duration_secs: f64) -> Vec<f64> {
+    if duration_secs <= PROBE_SEGMENT_SECS {
+        return vec![0.0];
+    }
+
+    let usable_span = duration_secs - PROBE_SEGMENT_SECS;
+    (0..PROBE_SEGMENT_COUNT)
+        .map(|i| usable_span * (i as f64 + 1.0) / (PROBE_SEGMENT_COUNT as f64 + 1.0))
+        .collect()
+}
+
+/// Encodes each probe segment at `crf` and scores it against the source with
+/// libvmaf, returning the average VMAF across segments.
+fn measure_vmaf_at_crf(
+    ffmpeg_path: &str,
+    mov_filename: &str,
+    segment_starts: &[f64],
+    crf: u32,
+    job_token: &str,
+) -> Result<f64, AppError> {
+    let mut scores = Vec::with_capacity(segment_starts.len());
+
+    for (index, start) in segment_starts.iter().enumerate() {
+        let probe_path = std::env::temp_dir().join(format!("mov_to_mp4_probe_{}_{}_{}.mp4", job_token, crf, index));
+        let vmaf_log_path = std::env::temp_dir().join(format!("mov_to_mp4_vmaf_{}_{}_{}.json", job_token, crf, index));
+
+        let score_result = encode_and_score_probe(
+            ffmpeg_path,
+            mov_filename,
+            *start,
+            crf,
+            &probe_path,
+            &vmaf_log_path,
+        );
+
+        let _ = fs::remove_file(&probe_path);
+        let _ = fs::remove_file(&vmaf_log_path);
+
+        scores.push(score_result?);
+    }
+
+    Ok(scores.iter().sum::<f64>() / scores.len() as f64)
+}
+
+fn encode_and_score_probe(
+    ffmpeg_path: &str,
+    mov_filename: &str,
+    start_secs: f64,
+    crf: u32,
+    probe_path: &Path,
+    vmaf_log_path: &Path,
+) -> Result<f64, AppError> {
+    let start_str = start_secs.to_string();
+    let seg_len_str = PROBE_SEGMENT_SECS.to_string();
+    let crf_str = crf.to_string();
+    let probe_path_str = probe_path.to_str()
+        .ok_or_else(|| AppError::PathError("Invalid probe path".to_string()))?;
+
+    // Encode a short candidate segment at the CRF under test.
+    let encode_output = Command::new(ffmpeg_path)
+        .args([
+            "-ss", &start_str,
+            "-t", &seg_len_str,
+            "-i", mov_filename,
+            "-c:v", "libx264",
+            "-preset", "faster",
+            "-crf", &crf_str,
+            "-an",
+            "-y", probe_path_str,
+        ])
+        .output()?;
+
+    if !encode_output.status.success() {
+        return Err(AppError::FFmpegError(String::from_utf8_lossy(&encode_output.stderr).to_string()));
+    }
+
+    // Score the probe against the same segment of the original source.
+    let vmaf_log_str = vmaf_log_path.to_str()
+        .ok_or_else(|| AppError::PathError("Invalid VMAF log path".to_string()))?;
+    let lavfi = format!("libvmaf=log_fmt=json:log_path={}", vmaf_log_str);
+
+    // The probe file already *is* the trimmed segment starting at its own
+    // timestamp 0, so only the reference (source) input needs `-ss`/`-t` to
+    // line up with it; seeking the probe too would run past its own EOF.
+    let vmaf_output = Command::new(ffmpeg_path)
+        .args([
+            "-i", probe_path_str,
+            "-ss", &start_str,
+            "-t", &seg_len_str,
+            "-i", mov_filename,
+            "-lavfi", &lavfi,
+            "-f", "null",
+            "-",
+        ])
+        .output()?;
+
+    if !vmaf_output.status.success() {
+        return Err(AppError::FFmpegError(String::from_utf8_lossy(&vmaf_output.stderr).to_string()));
+    }
+
+    let log_contents = fs::read_to_string(vmaf_log_path)?;
+    parse_vmaf_mean(&log_contents)
+        .ok_or_else(|| AppError::FFmpegError("Could not parse VMAF score from libvmaf log".to_string()))
+}
+
+/// Extracts `pooled_metrics.vmaf.mean` from a libvmaf JSON log without
+/// pulling in a JSON dependency for a single scalar lookup.
+fn parse_vmaf_mean(log_contents: &str) -> Option<f64> {
+    let pooled = &log_contents[log_contents.find("\"pooled_metrics\"")?..];
+    let vmaf = &pooled[pooled.find("\"vmaf\"")?..];
+    let after_mean = &vmaf[vmaf.find("\"mean\"")? + "\"mean\"".len()..];
+    let after_colon = &after_mean[after_mean.find(':')? + 1..];
+    let end = after_colon.find([',', '}'])?;
+    after_colon[..end].trim().parse::<f64>().ok()
+}
+
+/// Probes the duration (in seconds) of a media file by parsing the
+/// `Duration:` line that `ffmpeg -i` prints to stderr.
+fn get_duration_secs(ffmpeg_path: &str, mov_filename: &str) -> Result<f64, AppError> {
+    let output = Command::new(ffmpeg_path)
+        .args(["-i", mov_filename])
+        .output()?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let duration_line = stderr
+        .lines()
+        .find(|line| line.trim_start().starts_with("Duration:"))
+        .ok_or_else(|| AppError::FFmpegError(format!("Could not determine duration of {}", mov_filename)))?;
+
+    let duration_str = duration_line
+        .split("Duration:")
+        .nth(1)
+        .and_then(|rest| rest.split(',').next())
+        .map(|s| s.trim())
+        .ok_or_else(|| AppError::FFmpegError(format!("Could not parse duration of {}", mov_filename)))?;
+
+    parse_ffmpeg_duration(duration_str)
+        .ok_or_else(|| AppError::FFmpegError(format!("Could not parse duration of {}", mov_filename)))
+}
+
+/// Parses an FFmpeg `HH:MM:SS.ss` duration string into seconds.
+fn parse_ffmpeg_duration(duration_str: &str) -> Option<f64> {
+    let mut parts = duration_str.split(':');
+    let hours: f64 = parts.next()?.parse().ok()?;
+    let minutes: f64 = parts.next()?.parse().ok()?;
+    let seconds: f64 = parts.next()?.parse().ok()?;
+    Some(hours * 3600.0 + minutes * 60.0 + seconds)
+}
+
 fn get_ffmpeg_path() -> Result<String, AppError> {
     if let Ok(path) = which::which("ffmpeg") {
         return Ok(path.to_str()
@@ -219,7 +737,17 @@ fn get_ffmpeg_path() -> Result<String, AppError> {
         PathBuf::from("bin/ffmpeg/ffmpeg")
     };
 
-    if !ffmpeg_path.exists() {
+    if ffmpeg_path.exists() {
+        return ffmpeg_path.to_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| AppError::PathError("Failed to convert FFmpeg path to string".to_string()));
+    }
+
+    let should_download = prompt_yes_no(
+        "FFmpeg was not found on your PATH or at bin/ffmpeg/. Download a static build now? (yes/no): ",
+    )?;
+
+    if !should_download {
         return Err(AppError::FFmpegError(format!(
             "FFmpeg binary not found. Please ensure it's either:\n\
             1. Installed and available in your system PATH, or\n\
@@ -228,12 +756,443 @@ fn get_ffmpeg_path() -> Result<String, AppError> {
         )));
     }
 
-    ffmpeg_path.to_str()
+    download_ffmpeg(&ffmpeg_path)
+}
+
+/// Downloads a static FFmpeg build for the current platform into `bin/ffmpeg/`,
+/// extracts the binary to `dest_path`, and verifies it runs.
+fn download_ffmpeg(dest_path: &Path) -> Result<String, AppError> {
+    let bin_dir = dest_path.parent()
+        .ok_or_else(|| AppError::PathError("Invalid FFmpeg destination path".to_string()))?;
+    fs::create_dir_all(bin_dir)?;
+
+    let url = ffmpeg_download_url()?;
+    let archive_name = url.rsplit('/').next().unwrap_or("ffmpeg-archive");
+    let archive_path = bin_dir.join(archive_name);
+
+    println!("Downloading FFmpeg from {}...", url);
+    download_with_progress(url, &archive_path)?;
+    extract_ffmpeg_binary(&archive_path, bin_dir)?;
+    let _ = fs::remove_file(&archive_path);
+
+    // The evermeet.cx macOS build doesn't bundle ffprobe, so a plain ffmpeg
+    // download would leave `get_ffprobe_path` (needed by `probe_video_stream`)
+    // hard-failing on the very next run. Fetch it separately.
+    #[cfg(target_os = "macos")]
+    download_ffprobe_for_macos(bin_dir)?;
+
+    #[cfg(unix)]
+    set_executable(dest_path)?;
+
+    verify_ffmpeg_binary(dest_path)?;
+
+    dest_path.to_str()
         .map(|s| s.to_string())
         .ok_or_else(|| AppError::PathError("Failed to convert FFmpeg path to string".to_string()))
 }
 
+/// Selects the static FFmpeg build archive URL for the current host platform.
+fn ffmpeg_download_url() -> Result<&'static str, AppError> {
+    if cfg!(target_os = "windows") {
+        Ok("https://www.gyan.dev/ffmpeg/builds/ffmpeg-release-essentials.zip")
+    } else if cfg!(target_os = "macos") {
+        Ok("https://evermeet.cx/ffmpeg/getrelease/zip")
+    } else if cfg!(all(target_os = "linux", target_arch = "x86_64")) {
+        Ok("https://johnvansickle.com/ffmpeg/releases/ffmpeg-release-amd64-static.tar.xz")
+    } else if cfg!(all(target_os = "linux", target_arch = "aarch64")) {
+        Ok("https://johnvansickle.com/ffmpeg/releases/ffmpeg-release-arm64-static.tar.xz")
+    } else {
+        Err(AppError::FFmpegError(
+            "No static FFmpeg build is available for this platform/architecture".to_string(),
+        ))
+    }
+}
+
+/// evermeet.cx publishes ffmpeg and ffprobe as separate release zips; this
+/// fetches the latter into `bin_dir` so a macOS user who downloads FFmpeg
+/// through the app still ends up with the ffprobe binary `get_ffprobe_path`
+/// requires. No-op if a previous run already fetched it.
+#[cfg(target_os = "macos")]
+fn download_ffprobe_for_macos(bin_dir: &Path) -> Result<(), AppError> {
+    let ffprobe_dest = bin_dir.join("ffprobe");
+    if ffprobe_dest.exists() {
+        return Ok(());
+    }
+
+    let url = "https://evermeet.cx/ffmpeg/getrelease/ffprobe/zip";
+    let archive_path = bin_dir.join("ffprobe-release.zip");
+
+    println!("Downloading ffprobe from {}...", url);
+    download_with_progress(url, &archive_path)?;
+    extract_binaries_from_zip(&archive_path, bin_dir)?;
+    let _ = fs::remove_file(&archive_path);
+    set_executable(&ffprobe_dest)?;
+
+    Ok(())
+}
+
+/// Streams `url` to `dest`, showing download progress.
+fn download_with_progress(url: &str, dest: &Path) -> Result<(), AppError> {
+    let response = ureq::get(url)
+        .call()
+        .map_err(|e| AppError::FFmpegError(format!("Failed to download FFmpeg: {}", e)))?;
+
+    let total_len = response
+        .header("Content-Length")
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(0);
+
+    let pb = ProgressBar::new(total_len);
+    pb.set_style(ProgressStyle::default_bar()
+        .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes}")
+        .unwrap()
+        .progress_chars("█▓▒░"));
+
+    let mut reader = response.into_reader();
+    let mut file = fs::File::create(dest)?;
+    let mut buffer = [0u8; 8192];
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        file.write_all(&buffer[..read])?;
+        pb.inc(read as u64);
+    }
+    pb.finish_and_clear();
+
+    Ok(())
+}
+
+/// File names that make a static FFmpeg build archive "complete" for our
+/// purposes: the converter binary and the prober `get_ffprobe_path` requires.
+const FFMPEG_ARCHIVE_BINARY_NAMES: &[&str] = &["ffmpeg", "ffmpeg.exe", "ffprobe", "ffprobe.exe"];
+
+/// Extracts the `ffmpeg`/`ffprobe` binaries (and `.exe` variants) from a
+/// downloaded archive into `dest_dir`. `ffprobe` may be absent on platforms
+/// whose static builds don't bundle it; only a missing `ffmpeg` is fatal.
+fn extract_ffmpeg_binary(archive_path: &Path, dest_dir: &Path) -> Result<(), AppError> {
+    let extracted = match archive_path.extension().and_then(|e| e.to_str()) {
+        Some("zip") => extract_binaries_from_zip(archive_path, dest_dir)?,
+        _ => extract_binaries_from_tar_xz(archive_path, dest_dir)?,
+    };
+
+    if extracted.iter().any(|name| name == "ffmpeg" || name == "ffmpeg.exe") {
+        Ok(())
+    } else {
+        Err(AppError::FFmpegError("FFmpeg binary not found inside downloaded archive".to_string()))
+    }
+}
+
+/// Extracts any matching binaries and returns the file names actually found.
+fn extract_binaries_from_zip(archive_path: &Path, dest_dir: &Path) -> Result<Vec<String>, AppError> {
+    let file = fs::File::open(archive_path)?;
+    let mut archive = zip::ZipArchive::new(file)
+        .map_err(|e| AppError::FFmpegError(format!("Failed to open FFmpeg archive: {}", e)))?;
+
+    let mut extracted = Vec::new();
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)
+            .map_err(|e| AppError::FFmpegError(format!("Failed to read FFmpeg archive entry: {}", e)))?;
+        let entry_name = entry.name().to_string();
+        let file_name = Path::new(&entry_name).file_name().and_then(|f| f.to_str()).unwrap_or("");
+
+        if FFMPEG_ARCHIVE_BINARY_NAMES.contains(&file_name) {
+            let mut out_file = fs::File::create(dest_dir.join(file_name))?;
+            io::copy(&mut entry, &mut out_file)?;
+            extracted.push(file_name.to_string());
+        }
+    }
+
+    Ok(extracted)
+}
+
+fn extract_binaries_from_tar_xz(archive_path: &Path, dest_dir: &Path) -> Result<Vec<String>, AppError> {
+    let file = fs::File::open(archive_path)?;
+    let decompressed = xz2::read::XzDecoder::new(file);
+    let mut archive = tar::Archive::new(decompressed);
+
+    let mut extracted = Vec::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let entry_path = entry.path()?.to_path_buf();
+        let file_name = entry_path.file_name().and_then(|f| f.to_str()).unwrap_or("");
+
+        if FFMPEG_ARCHIVE_BINARY_NAMES.contains(&file_name) {
+            entry.unpack(dest_dir.join(file_name))?;
+            extracted.push(file_name.to_string());
+        }
+    }
+
+    Ok(extracted)
+}
+
+/// Marks the downloaded binary executable on Unix platforms.
+#[cfg(unix)]
+fn set_executable(path: &Path) -> Result<(), AppError> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut perms = fs::metadata(path)?.permissions();
+    perms.set_mode(0o755);
+    fs::set_permissions(path, perms)?;
+    Ok(())
+}
+
+/// Runs `ffmpeg -version` to confirm the downloaded binary actually works.
+fn verify_ffmpeg_binary(path: &Path) -> Result<(), AppError> {
+    let output = Command::new(path)
+        .arg("-version")
+        .output()
+        .map_err(|e| AppError::FFmpegError(format!("Downloaded FFmpeg binary failed to run: {}", e)))?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(AppError::FFmpegError("Downloaded FFmpeg binary failed verification".to_string()))
+    }
+}
+
+fn get_ffprobe_path() -> Result<String, AppError> {
+    if let Ok(path) = which::which("ffprobe") {
+        return Ok(path.to_str()
+            .ok_or_else(|| AppError::PathError("Invalid ffprobe path".to_string()))?
+            .to_string());
+    }
+
+    let ffprobe_path = if cfg!(target_os = "windows") {
+        PathBuf::from("bin/ffmpeg/ffprobe.exe")
+    } else {
+        PathBuf::from("bin/ffmpeg/ffprobe")
+    };
+
+    if !ffprobe_path.exists() {
+        return Err(AppError::FFmpegError(format!(
+            "ffprobe binary not found. Please ensure it's either:\n\
+            1. Installed and available in your system PATH, or\n\
+            2. Located at {:?}",
+            ffprobe_path
+        )));
+    }
+
+    ffprobe_path.to_str()
+        .map(|s| s.to_string())
+        .ok_or_else(|| AppError::PathError("Failed to convert ffprobe path to string".to_string()))
+}
+
+/// Resolution and frame-rate characteristics of a source's first video stream.
+struct VideoStreamInfo {
+    height: u32,
+    /// True when the nominal and average frame rate diverge enough to
+    /// indicate a variable-frame-rate source (e.g. phone camera recordings).
+    is_vfr: bool,
+}
+
+/// Probes resolution and frame rate of a media file's first video stream via ffprobe.
+fn probe_video_stream(ffprobe_path: &str, mov_filename: &str) -> Result<VideoStreamInfo, AppError> {
+    let output = Command::new(ffprobe_path)
+        .args([
+            "-v", "error",
+            "-select_streams", "v:0",
+            "-show_entries", "stream=width,height,r_frame_rate,avg_frame_rate",
+            "-of", "default=noprint_wrappers=1",
+            mov_filename,
+        ])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(AppError::FFmpegError(String::from_utf8_lossy(&output.stderr).to_string()));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut height = None;
+    let mut r_frame_rate = None;
+    let mut avg_frame_rate = None;
+
+    for line in stdout.lines() {
+        let mut parts = line.splitn(2, '=');
+        let key = parts.next().unwrap_or("");
+        let value = parts.next().unwrap_or("").trim();
+        match key {
+            "height" => height = value.parse::<u32>().ok(),
+            "r_frame_rate" => r_frame_rate = parse_frame_rate(value),
+            "avg_frame_rate" => avg_frame_rate = parse_frame_rate(value),
+            _ => {}
+        }
+    }
+
+    let height = height
+        .ok_or_else(|| AppError::FFmpegError(format!("Could not parse resolution of {}", mov_filename)))?;
+
+    let is_vfr = match (r_frame_rate, avg_frame_rate) {
+        (Some(nominal), Some(average)) if nominal > 0.0 => ((nominal - average).abs() / nominal) > 0.01,
+        _ => false,
+    };
+
+    Ok(VideoStreamInfo { height, is_vfr })
+}
+
+/// Parses an ffprobe `"num/den"` frame rate into frames per second.
+fn parse_frame_rate(frame_rate: &str) -> Option<f64> {
+    let mut parts = frame_rate.split('/');
+    let numerator: f64 = parts.next()?.parse().ok()?;
+    let denominator: f64 = parts.next()?.parse().ok()?;
+    if denominator == 0.0 {
+        None
+    } else {
+        Some(numerator / denominator)
+    }
+}
+
+/// Fixed video bitrate used for hardware H.264 encoders, which don't support
+/// libx264's `-crf` rate control.
+const HW_ENCODER_FALLBACK_BITRATE: &str = "8M";
+
+/// This platform's candidate hardware H.264 encoders, tried in order.
+fn hw_encoder_candidates() -> &'static [&'static str] {
+    if cfg!(target_os = "macos") {
+        &["h264_videotoolbox"]
+    } else if cfg!(target_os = "windows") {
+        &["h264_nvenc", "h264_qsv"]
+    } else if cfg!(target_os = "linux") {
+        &["h264_vaapi"]
+    } else {
+        &[]
+    }
+}
+
+fn is_hardware_encoder(video_codec: &str) -> bool {
+    hw_encoder_candidates().contains(&video_codec)
+}
+
+/// Picks a real hardware encoder for `profile`'s software codec when the
+/// user opted in and ffmpeg confirms the encoder actually works, falling
+/// back to the profile's own software codec otherwise. Only H.264 profiles
+/// have a hardware counterpart today.
+fn select_video_encoder(ffmpeg_path: &str, profile: &CodecProfile, use_hw_encoder: bool) -> String {
+    if use_hw_encoder && profile.video_codec == "libx264" {
+        for candidate in hw_encoder_candidates() {
+            if probe_encoder_works(ffmpeg_path, candidate) {
+                return candidate.to_string();
+            }
+        }
+    }
+
+    profile.video_codec.to_string()
+}
+
+/// Confirms an encoder actually works on this machine by running a tiny
+/// real encode rather than just checking `ffmpeg -encoders` for the name.
+fn probe_encoder_works(ffmpeg_path: &str, encoder: &str) -> bool {
+    let mut args = vec!["-f", "lavfi", "-i", "color=s=64x64:d=0.1"];
+    if encoder == "h264_vaapi" {
+        args.extend_from_slice(&["-vaapi_device", "/dev/dri/renderD128", "-vf", "format=nv12,hwupload"]);
+    }
+    args.extend_from_slice(&["-c:v", encoder, "-frames:v", "1", "-f", "null", "-"]);
+
+    Command::new(ffmpeg_path)
+        .args(&args)
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
 fn remove_mov(mov_filename: &str) -> Result<(), AppError> {
     fs::remove_file(mov_filename)?;
     Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_vmaf_mean_reads_pooled_metrics() {
+        let log = r#"{
+            "pooled_metrics": {
+                "vmaf": { "min": 80.1, "max": 99.9, "mean": 95.432, "harmonic_mean": 95.1 }
+            }
+        }"#;
+        assert_eq!(parse_vmaf_mean(log), Some(95.432));
+    }
+
+    #[test]
+    fn parse_vmaf_mean_handles_integer_values() {
+        assert_eq!(
+            parse_vmaf_mean(r#"{"pooled_metrics":{"vmaf":{"mean":100}}}"#),
+            Some(100.0)
+        );
+    }
+
+    #[test]
+    fn parse_vmaf_mean_missing_field_returns_none() {
+        assert_eq!(parse_vmaf_mean(r#"{"pooled_metrics":{"psnr":{"mean":40.0}}}"#), None);
+        assert_eq!(parse_vmaf_mean(""), None);
+    }
+
+    #[test]
+    fn probe_segment_starts_short_source_is_single_zero_start() {
+        assert_eq!(probe_segment_starts(0.0), vec![0.0]);
+        assert_eq!(probe_segment_starts(PROBE_SEGMENT_SECS), vec![0.0]);
+    }
+
+    #[test]
+    fn probe_segment_starts_spreads_evenly_within_bounds() {
+        let duration = 100.0;
+        let starts = probe_segment_starts(duration);
+
+        assert_eq!(starts.len(), PROBE_SEGMENT_COUNT);
+        for start in &starts {
+            // Every segment must leave room for a full PROBE_SEGMENT_SECS
+            // sample before the source ends.
+            assert!(*start >= 0.0);
+            assert!(*start + PROBE_SEGMENT_SECS <= duration);
+        }
+        // Strictly increasing, since segments are spread across the timeline.
+        assert!(starts.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn bisect_crf_finds_highest_crf_meeting_target() {
+        // Higher CRF -> lower VMAF, as in a real encoder. With this slope the
+        // target is reachable within MIN_SEARCH_CRF..=MAX_SEARCH_CRF (unlike
+        // `100.0 - crf`, whose max score at CRF 18 is only 82).
+        let crf = bisect_crf_for_target_vmaf(95.0, |crf| Ok(120.0 - crf as f64)).unwrap();
+        assert!((MIN_SEARCH_CRF..=MAX_SEARCH_CRF).contains(&crf));
+        let score = 120.0 - crf as f64;
+        assert!(score >= 95.0 - VMAF_TOLERANCE);
+    }
+
+    #[test]
+    fn bisect_crf_clamps_to_min_when_target_unreachable() {
+        // Even the lowest CRF can't hit an impossibly high target, so the
+        // search should settle on MIN_SEARCH_CRF rather than erroring.
+        let crf = bisect_crf_for_target_vmaf(999.0, |_| Ok(50.0)).unwrap();
+        assert_eq!(crf, MIN_SEARCH_CRF);
+    }
+
+    #[test]
+    fn bisect_crf_clamps_to_max_when_target_trivially_met() {
+        // Every CRF exceeds the target, so the search should climb all the
+        // way to MAX_SEARCH_CRF for the smallest file.
+        let crf = bisect_crf_for_target_vmaf(0.0, |_| Ok(100.0)).unwrap();
+        assert_eq!(crf, MAX_SEARCH_CRF);
+    }
+
+    #[test]
+    fn bisect_crf_propagates_measurement_errors() {
+        let result = bisect_crf_for_target_vmaf(95.0, |_| {
+            Err(AppError::FFmpegError("probe failed".to_string()))
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn parse_ffmpeg_duration_parses_hh_mm_ss() {
+        assert_eq!(parse_ffmpeg_duration("00:01:02.50"), Some(62.5));
+        assert_eq!(parse_ffmpeg_duration("01:00:00.00"), Some(3600.0));
+    }
+
+    #[test]
+    fn parse_ffmpeg_duration_rejects_garbage() {
+        assert_eq!(parse_ffmpeg_duration("not a duration"), None);
+    }
 }
\ No newline at end of file